@@ -0,0 +1,202 @@
+//! Conversions between native Rust types and `Param`, so callers don't have to
+//! hand-assemble `Base`/`Container` trees.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::message::{validate_array, Base, Container, Error, Param, Result};
+use crate::signature;
+
+/// Converts a native Rust value into a `Param` ready to be added to a `Message`.
+pub trait Append {
+    fn append(self) -> Param;
+}
+
+/// Marks `Append` types that convert to a `Base` (as opposed to a `Container`), i.e.
+/// the only types valid as a dict-entry key. This is enforced at the type level so
+/// `HashMap<K, V>`'s `Append` impl can't be handed a container-typed `K`.
+pub trait BasicAppend: Append {
+    fn append_basic(self) -> Base;
+}
+
+/// Converts a `Param` back into a native Rust value, failing if the signature doesn't match.
+pub trait Get: Sized {
+    fn get(param: &Param) -> Result<Self>;
+}
+
+/// Gives the static D-Bus signature of an `Append`-able type, independent of any
+/// particular instance. Needed alongside `Append` because a container can't derive
+/// a signature from its elements when it has none, e.g. an empty `Vec<T>`.
+pub trait Signature {
+    fn signature() -> String;
+}
+
+macro_rules! impl_basic {
+    ($ty:ty, $base:ident, $code:expr) => {
+        impl Append for $ty {
+            fn append(self) -> Param {
+                Param::Base(Base::$base(self))
+            }
+        }
+
+        impl BasicAppend for $ty {
+            fn append_basic(self) -> Base {
+                Base::$base(self)
+            }
+        }
+
+        impl Get for $ty {
+            fn get(param: &Param) -> Result<Self> {
+                match param {
+                    Param::Base(Base::$base(v)) => Ok(*v),
+                    _ => Err(Error::TypeMismatch),
+                }
+            }
+        }
+
+        impl Signature for $ty {
+            fn signature() -> String {
+                $code.to_string()
+            }
+        }
+    };
+}
+
+impl_basic!(u8, Byte, 'y');
+impl_basic!(i16, Int16, 'n');
+impl_basic!(u16, Uint16, 'q');
+impl_basic!(i32, Int32, 'i');
+impl_basic!(u32, Uint32, 'u');
+impl_basic!(i64, Int64, 'x');
+impl_basic!(u64, Uint64, 't');
+impl_basic!(f64, Double, 'd');
+impl_basic!(bool, Boolean, 'b');
+
+impl Append for String {
+    fn append(self) -> Param {
+        Param::Base(Base::String(self))
+    }
+}
+
+impl BasicAppend for String {
+    fn append_basic(self) -> Base {
+        Base::String(self)
+    }
+}
+
+impl Get for String {
+    fn get(param: &Param) -> Result<Self> {
+        match param {
+            Param::Base(Base::String(v)) => Ok(v.clone()),
+            _ => Err(Error::TypeMismatch),
+        }
+    }
+}
+
+impl Signature for String {
+    fn signature() -> String {
+        "s".to_string()
+    }
+}
+
+impl Append for &str {
+    fn append(self) -> Param {
+        Param::Base(Base::String(self.to_string()))
+    }
+}
+
+impl<T: Append + Signature> Append for Vec<T> {
+    fn append(self) -> Param {
+        let elem_sig = signature::Type::from_str(&T::signature()).expect("Signature impls only produce valid signatures");
+        let elements = self.into_iter().map(Append::append).collect();
+        Param::Container(Container::Array(elem_sig, elements))
+    }
+}
+
+impl<T: Get> Get for Vec<T> {
+    fn get(param: &Param) -> Result<Self> {
+        match param {
+            Param::Container(Container::Array(elem_sig, elements)) => {
+                validate_array(elem_sig, elements).map_err(|_| Error::TypeMismatch)?;
+                elements.iter().map(T::get).collect()
+            }
+            _ => Err(Error::TypeMismatch),
+        }
+    }
+}
+
+impl<K: BasicAppend + Signature + Eq + Hash, V: Append + Signature> Append for HashMap<K, V> {
+    fn append(self) -> Param {
+        let elem_sig = signature::Type::from_str(&format!("{{{}{}}}", K::signature(), V::signature()))
+            .expect("Signature impls only produce valid signatures");
+        let elements = self
+            .into_iter()
+            .map(|(k, v)| Param::Container(Container::DictEntry(k.append_basic(), Box::new(v.append()))))
+            .collect();
+        Param::Container(Container::Array(elem_sig, elements))
+    }
+}
+
+impl<K: Get + Eq + Hash, V: Get> Get for HashMap<K, V> {
+    fn get(param: &Param) -> Result<Self> {
+        match param {
+            Param::Container(Container::Array(_, elements)) => elements
+                .iter()
+                .map(|el| match el {
+                    Param::Container(Container::DictEntry(key, val)) => {
+                        let k = K::get(&Param::Base(base_clone(key)?))?;
+                        let v = V::get(val)?;
+                        Ok((k, v))
+                    }
+                    _ => Err(Error::TypeMismatch),
+                })
+                .collect(),
+            _ => Err(Error::TypeMismatch),
+        }
+    }
+}
+
+fn base_clone(base: &Base) -> Result<Base> {
+    Ok(match base {
+        Base::Byte(v) => Base::Byte(*v),
+        Base::Int16(v) => Base::Int16(*v),
+        Base::Uint16(v) => Base::Uint16(*v),
+        Base::Int32(v) => Base::Int32(*v),
+        Base::Uint32(v) => Base::Uint32(*v),
+        Base::Int64(v) => Base::Int64(*v),
+        Base::Uint64(v) => Base::Uint64(*v),
+        Base::Double(v) => Base::Double(*v),
+        Base::String(v) => Base::String(v.clone()),
+        Base::Signature(v) => Base::Signature(v.clone()),
+        Base::ObjectPath(v) => Base::ObjectPath(v.clone()),
+        Base::Boolean(v) => Base::Boolean(*v),
+        Base::UnixFd(v) => Base::UnixFd(v.try_clone().map_err(Error::FdDupFailed)?),
+    })
+}
+
+macro_rules! impl_tuple {
+    ($($idx:tt $name:ident),+) => {
+        impl<$($name: Append),+> Append for ($($name,)+) {
+            fn append(self) -> Param {
+                let elements = vec![$(self.$idx.append()),+];
+                Param::Container(Container::Struct(elements))
+            }
+        }
+
+        impl<$($name: Get),+> Get for ($($name,)+) {
+            fn get(param: &Param) -> Result<Self> {
+                match param {
+                    Param::Container(Container::Struct(elements)) => {
+                        Ok(($($name::get(elements.get($idx).ok_or(Error::TypeMismatch)?)?,)+))
+                    }
+                    _ => Err(Error::TypeMismatch),
+                }
+            }
+        }
+    };
+}
+
+impl_tuple!(0 A);
+impl_tuple!(0 A, 1 B);
+impl_tuple!(0 A, 1 B, 2 C);
+impl_tuple!(0 A, 1 B, 2 C, 3 D);
@@ -0,0 +1,190 @@
+//! A fluent builder for constructing `Message`s.
+
+use crate::message::{
+    validate_header_fields, validate_object_path, HeaderFlags, Message, MessageType, Param, Result,
+};
+
+/// Builds a `Message` one field at a time, validating header fields and the
+/// object path only once `build()` is called.
+pub struct MessageBuilder {
+    typ: MessageType,
+    interface: Option<String>,
+    member: Option<String>,
+    object: Option<String>,
+    destination: Option<String>,
+    sender: Option<String>,
+    error_name: Option<String>,
+    reply_serial: Option<u32>,
+    flags: Vec<HeaderFlags>,
+    params: Vec<Param>,
+}
+
+impl MessageBuilder {
+    fn new(typ: MessageType) -> MessageBuilder {
+        MessageBuilder {
+            typ,
+            interface: None,
+            member: None,
+            object: None,
+            destination: None,
+            sender: None,
+            error_name: None,
+            reply_serial: None,
+            flags: Vec::new(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Starts a `METHOD_CALL` to `member` on the object at `path`.
+    pub fn method_call(path: &str, member: &str) -> MessageBuilder {
+        let mut builder = MessageBuilder::new(MessageType::Call);
+        builder.object = Some(path.to_string());
+        builder.member = Some(member.to_string());
+        builder
+    }
+
+    /// Starts a `SIGNAL` named `member`, emitted on `interface` from the object at `path`.
+    pub fn signal(path: &str, interface: &str, member: &str) -> MessageBuilder {
+        let mut builder = MessageBuilder::new(MessageType::Signal);
+        builder.object = Some(path.to_string());
+        builder.interface = Some(interface.to_string());
+        builder.member = Some(member.to_string());
+        builder
+    }
+
+    /// Starts an `ERROR` named `error_name`, replying to `reply_serial`.
+    pub fn error(reply_serial: u32, error_name: &str) -> MessageBuilder {
+        let mut builder = MessageBuilder::new(MessageType::Error);
+        builder.error_name = Some(error_name.to_string());
+        builder.reply_serial = Some(reply_serial);
+        builder
+    }
+
+    /// Starts a `METHOD_RETURN` replying to `reply_serial`.
+    pub fn method_return(reply_serial: u32) -> MessageBuilder {
+        let mut builder = MessageBuilder::new(MessageType::Reply);
+        builder.reply_serial = Some(reply_serial);
+        builder
+    }
+
+    pub fn interface(mut self, interface: &str) -> MessageBuilder {
+        self.interface = Some(interface.to_string());
+        self
+    }
+
+    pub fn destination(mut self, destination: &str) -> MessageBuilder {
+        self.destination = Some(destination.to_string());
+        self
+    }
+
+    pub fn sender(mut self, sender: &str) -> MessageBuilder {
+        self.sender = Some(sender.to_string());
+        self
+    }
+
+    pub fn flag(mut self, flag: HeaderFlags) -> MessageBuilder {
+        self.flags.push(flag);
+        self
+    }
+
+    pub fn append(mut self, param: Param) -> MessageBuilder {
+        self.params.push(param);
+        self
+    }
+
+    pub fn append_all(mut self, params: Vec<Param>) -> MessageBuilder {
+        self.params.extend(params);
+        self
+    }
+
+    /// Validates the accumulated header fields and object path, then produces the `Message`.
+    pub fn build(self) -> Result<Message> {
+        if let Some(path) = &self.object {
+            validate_object_path(path)?;
+        }
+
+        // Building each argument's signature here, rather than waiting for
+        // `wire::marshal`, rejects a non-homogeneous array as soon as the
+        // message is built instead of at send time.
+        for param in &self.params {
+            let mut sig = String::new();
+            param.make_signature(&mut sig)?;
+        }
+
+        let mut header_fields = Vec::new();
+        if self.object.is_some() {
+            header_fields.push(crate::message::HeaderField::Path(
+                self.object.clone().unwrap(),
+            ));
+        }
+        if let Some(interface) = &self.interface {
+            header_fields.push(crate::message::HeaderField::Interface(interface.clone()));
+        }
+        if let Some(member) = &self.member {
+            header_fields.push(crate::message::HeaderField::Member(member.clone()));
+        }
+        if let Some(error_name) = &self.error_name {
+            header_fields.push(crate::message::HeaderField::ErrorName(error_name.clone()));
+        }
+        if let Some(reply_serial) = self.reply_serial {
+            header_fields.push(crate::message::HeaderField::ReplySerial(reply_serial));
+        }
+        validate_header_fields(self.typ, &header_fields)?;
+
+        let mut msg = Message::new(
+            self.typ,
+            self.interface,
+            self.member,
+            self.object,
+            self.destination,
+            self.params,
+        );
+        msg.sender = self.sender;
+        msg.error_name = self.error_name;
+        msg.reply_serial = self.reply_serial;
+        msg.flags = self.flags;
+        Ok(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Error;
+
+    #[test]
+    fn signal_missing_interface_is_rejected() {
+        // A METHOD_CALL needs no interface, so it builds fine. A SIGNAL does:
+        // `MessageBuilder::signal` always sets `interface`, so the interface-less
+        // signal is assembled through the private `new` here, and `build()` must
+        // still catch it rather than emitting an invalid SIGNAL.
+        let result = MessageBuilder::method_call("/test", "Member")
+            .flag(HeaderFlags::NoReplyExpected)
+            .build();
+        assert!(result.is_ok());
+
+        let mut builder = MessageBuilder::new(MessageType::Signal);
+        builder.object = Some("/test".to_string());
+        builder.member = Some("Member".to_string());
+        assert!(matches!(
+            builder.build(),
+            Err(Error::InvalidHeaderFields)
+        ));
+    }
+
+    #[test]
+    fn call_missing_member_is_rejected() {
+        let mut builder = MessageBuilder::new(MessageType::Call);
+        builder.object = Some("/test".to_string());
+        assert!(matches!(
+            builder.build(),
+            Err(Error::InvalidHeaderFields)
+        ));
+    }
+
+    #[test]
+    fn invalid_object_path_is_rejected() {
+        let result = MessageBuilder::method_call("no/leading/slash", "Member").build();
+        assert!(matches!(result, Err(Error::InvalidObjectPath)));
+    }
+}
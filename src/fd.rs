@@ -0,0 +1,79 @@
+//! RAII ownership of a Unix file descriptor, as carried by `Base::UnixFd`.
+
+use std::os::unix::io::RawFd;
+
+extern "C" {
+    fn dup(fd: i32) -> i32;
+    fn close(fd: i32) -> i32;
+}
+
+/// Owns a raw file descriptor, closing it on `Drop` and `dup`-ing it via
+/// `try_clone`, so a single fd received over D-Bus can be handed around like
+/// any other value.
+///
+/// Deliberately does not implement `std::clone::Clone`: `dup()` can fail (e.g.
+/// on fd-table exhaustion), and `Clone::clone` has no way to report that short
+/// of panicking, which is unacceptable in `wire::unmarshal`'s decode path for
+/// untrusted input.
+#[derive(Debug)]
+pub struct OwnedFd {
+    fd: RawFd,
+}
+
+impl OwnedFd {
+    /// Takes ownership of `fd`. The caller must not close it afterwards.
+    pub fn new(fd: RawFd) -> OwnedFd {
+        OwnedFd { fd }
+    }
+
+    pub fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+
+    /// Gives up ownership of the descriptor without closing it.
+    pub fn into_raw_fd(self) -> RawFd {
+        let fd = self.fd;
+        std::mem::forget(self);
+        fd
+    }
+
+    /// Duplicates the underlying descriptor, returning an independently-owned copy.
+    ///
+    /// Fails if the underlying `dup()` call fails, e.g. because the process has
+    /// run out of file descriptors.
+    pub fn try_clone(&self) -> std::io::Result<OwnedFd> {
+        let new_fd = unsafe { dup(self.fd) };
+        if new_fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(OwnedFd { fd: new_fd })
+    }
+}
+
+impl Drop for OwnedFd {
+    fn drop(&mut self) {
+        unsafe {
+            close(self.fd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_clone_duplicates_a_valid_fd() {
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let owned = OwnedFd::new(std::os::unix::io::IntoRawFd::into_raw_fd(file));
+        let cloned = owned.try_clone().unwrap();
+        assert_ne!(owned.as_raw_fd(), cloned.as_raw_fd());
+    }
+
+    #[test]
+    fn try_clone_reports_dup_failure() {
+        let bad = OwnedFd::new(-1);
+        assert!(bad.try_clone().is_err());
+        std::mem::forget(bad); // avoid closing -1 in Drop
+    }
+}
@@ -0,0 +1,6 @@
+pub mod arg;
+pub mod builder;
+pub mod fd;
+pub mod message;
+pub mod signature;
+pub mod wire;
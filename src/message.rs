@@ -1,3 +1,4 @@
+use crate::fd::OwnedFd;
 use crate::signature;
 
 #[derive(Copy, Clone, Debug)]
@@ -10,17 +11,27 @@ pub enum MessageType {
 
 #[derive(Debug)]
 pub enum Base {
+    Byte(u8),
+    Int16(i16),
+    Uint16(u16),
     Int32(i32),
     Uint32(u32),
+    Int64(i64),
+    Uint64(u64),
+    Double(f64),
     String(String),
     Signature(String),
     ObjectPath(String),
     Boolean(bool),
+    UnixFd(OwnedFd),
 }
 
 #[derive(Debug)]
 pub enum Container {
-    Array(Vec<Param>),
+    /// An array's element type is carried explicitly (rather than derived from
+    /// `elements[0]`) so an empty array can still be marshalled with a valid
+    /// signature, matching the wire format where an empty array is ordinary.
+    Array(signature::Type, Vec<Param>),
     Struct(Vec<Param>),
     DictEntry(Base, Box<Param>),
     Variant(Box<Variant>),
@@ -45,6 +56,10 @@ pub struct Message {
     pub member: Option<String>,
     pub object: Option<String>,
     pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub flags: Vec<HeaderFlags>,
     pub params: Vec<Param>,
 }
 
@@ -57,14 +72,32 @@ impl Message {
             params,
             object,
             destination,
+            sender: None,
+            error_name: None,
+            reply_serial: None,
+            flags: Vec::new(),
         }
     }
+
+    /// Converts `val` to a `Param` via `Append` and adds it to this message's arguments.
+    pub fn append<T: crate::arg::Append>(&mut self, val: T) {
+        self.params.push(val.append());
+    }
+
+    /// Converts the argument at `index` to `T` via `Get`.
+    pub fn get<T: crate::arg::Get>(&self, index: usize) -> Result<T> {
+        let param = self.params.get(index).ok_or(Error::TypeMismatch)?;
+        T::get(param)
+    }
 }
 
 impl Param {
-    pub fn make_signature(&self, buf: &mut String) {
+    pub fn make_signature(&self, buf: &mut String) -> Result<()> {
         match self {
-            Param::Base(b) => b.make_signature(buf),
+            Param::Base(b) => {
+                b.make_signature(buf);
+                Ok(())
+            }
             Param::Container(c) => c.make_signature(buf),
         }
     }
@@ -73,32 +106,40 @@ impl Param {
 impl Base {
     pub fn make_signature(&self, buf: &mut String) {
         match self {
-            Base::Boolean(_) => buf.push('c'),
+            Base::Byte(_) => buf.push('y'),
+            Base::Int16(_) => buf.push('n'),
+            Base::Uint16(_) => buf.push('q'),
+            Base::Boolean(_) => buf.push('b'),
             Base::Int32(_) => buf.push('i'),
             Base::Uint32(_) => buf.push('u'),
+            Base::Int64(_) => buf.push('x'),
+            Base::Uint64(_) => buf.push('t'),
+            Base::Double(_) => buf.push('d'),
             Base::ObjectPath(_) => buf.push('o'),
             Base::String(_) => buf.push('s'),
             Base::Signature(_) => buf.push('g'),
+            Base::UnixFd(_) => buf.push('h'),
         }
     }
 }
 impl Container {
-    pub fn make_signature(&self, buf: &mut String) {
+    pub fn make_signature(&self, buf: &mut String) -> Result<()> {
         match self {
-            Container::Array(elements) => {
+            Container::Array(sig, elements) => {
+                validate_array(sig, elements)?;
                 buf.push('a');
-                elements[0].make_signature(buf);
-            },
+                buf.push_str(sig.as_str());
+            }
             Container::DictEntry(key, val) => {
                 buf.push('{');
                 key.make_signature(buf);
-                val.make_signature(buf);
-                buf.push('{');
+                val.make_signature(buf)?;
+                buf.push('}');
             }
             Container::Struct(elements) => {
                 buf.push('(');
                 for el in elements {
-                    el.make_signature(buf);
+                    el.make_signature(buf)?;
                 }
                 buf.push(')');
             }
@@ -106,6 +147,7 @@ impl Container {
                 buf.push('v');
             }
         }
+        Ok(())
     }
 }
 
@@ -114,6 +156,10 @@ pub enum Error {
     InvalidObjectPath,
     InvalidSignature,
     InvalidHeaderFields,
+    DifferentArrayElementTypes,
+    TypeMismatch,
+    /// Duplicating a `Base::UnixFd`'s descriptor (e.g. while decoding it) failed.
+    FdDupFailed(std::io::Error),
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -122,12 +168,23 @@ pub enum ByteOrder {
     BigEndian,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HeaderFlags {
     NoReplyExpected,
     NoAutoStart,
     AllowInteractiveAuthorization,
 }
 
+impl HeaderFlags {
+    pub fn bit(&self) -> u8 {
+        match self {
+            HeaderFlags::NoReplyExpected => 0x1,
+            HeaderFlags::NoAutoStart => 0x2,
+            HeaderFlags::AllowInteractiveAuthorization => 0x4,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum HeaderField {
     Path(String),
@@ -143,8 +200,25 @@ pub enum HeaderField {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-pub fn validate_object_path(_op: &str) -> Result<()> {
-    // TODO
+pub fn validate_object_path(op: &str) -> Result<()> {
+    if op.is_empty() || !op.is_ascii() || !op.starts_with('/') {
+        return Err(Error::InvalidObjectPath);
+    }
+    if op == "/" {
+        return Ok(());
+    }
+    if op.ends_with('/') {
+        return Err(Error::InvalidObjectPath);
+    }
+    for element in op[1..].split('/') {
+        if element.is_empty()
+            || !element
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        {
+            return Err(Error::InvalidObjectPath);
+        }
+    }
     Ok(())
 }
 pub fn validate_signature(sig: &str) -> Result<()> {
@@ -155,8 +229,17 @@ pub fn validate_signature(sig: &str) -> Result<()> {
     }
 }
 
-pub fn validate_array(_array: &Vec<Param>) -> Result<()> {
-    // TODO check that all elements have the same type
+/// Checks that every element of `array` has the signature `elem_sig` declares,
+/// so a mismatched element is caught before marshalling rather than desyncing
+/// the wire signature from the actual body contents.
+pub fn validate_array(elem_sig: &signature::Type, array: &[Param]) -> Result<()> {
+    for element in array {
+        let mut sig = String::new();
+        element.make_signature(&mut sig)?;
+        if sig != elem_sig.as_str() {
+            return Err(Error::DifferentArrayElementTypes);
+        }
+    }
     Ok(())
 }
 
@@ -253,3 +336,61 @@ pub fn validate_header_fields(
         Err(Error::InvalidHeaderFields)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_object_path_accepts_root() {
+        assert!(validate_object_path("/").is_ok());
+    }
+
+    #[test]
+    fn validate_object_path_rejects_trailing_slash() {
+        assert!(matches!(
+            validate_object_path("/foo/"),
+            Err(Error::InvalidObjectPath)
+        ));
+    }
+
+    #[test]
+    fn validate_object_path_rejects_double_slash() {
+        assert!(matches!(
+            validate_object_path("/foo//bar"),
+            Err(Error::InvalidObjectPath)
+        ));
+    }
+
+    #[test]
+    fn validate_object_path_rejects_non_ascii() {
+        assert!(matches!(
+            validate_object_path("/f\u{00f6}o"),
+            Err(Error::InvalidObjectPath)
+        ));
+    }
+
+    #[test]
+    fn validate_object_path_rejects_missing_leading_slash() {
+        assert!(matches!(
+            validate_object_path("foo"),
+            Err(Error::InvalidObjectPath)
+        ));
+    }
+
+    #[test]
+    fn validate_array_rejects_mismatched_element_types() {
+        let sig = signature::Type::from_str("i").unwrap();
+        let elements = vec![Param::Base(Base::Int32(1)), Param::Base(Base::Uint32(2))];
+        assert!(matches!(
+            validate_array(&sig, &elements),
+            Err(Error::DifferentArrayElementTypes)
+        ));
+    }
+
+    #[test]
+    fn validate_array_accepts_empty() {
+        let sig = signature::Type::from_str("i").unwrap();
+        assert!(validate_array(&sig, &[]).is_ok());
+    }
+}
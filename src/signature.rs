@@ -0,0 +1,71 @@
+//! Parsing and validation of D-Bus type signature strings.
+
+#[derive(Debug)]
+pub struct Type(String);
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidSignature,
+}
+
+const MAX_SIGNATURE_LENGTH: usize = 255;
+
+impl Type {
+    /// Parses `sig` as a signature (a sequence of zero or more complete types),
+    /// validating it against the D-Bus signature grammar.
+    pub fn from_str(sig: &str) -> Result<Type, Error> {
+        if sig.len() > MAX_SIGNATURE_LENGTH {
+            return Err(Error::InvalidSignature);
+        }
+        let mut chars = sig.chars();
+        while chars.as_str().len() > 0 {
+            parse_complete_type(&mut chars)?;
+        }
+        Ok(Type(sig.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn parse_complete_type(chars: &mut std::str::Chars) -> Result<(), Error> {
+    match chars.next().ok_or(Error::InvalidSignature)? {
+        'y' | 'b' | 'n' | 'q' | 'i' | 'u' | 'x' | 't' | 'd' | 's' | 'o' | 'g' | 'h' | 'v' => Ok(()),
+        'a' => parse_complete_type(chars),
+        '(' => {
+            let mut saw_member = false;
+            loop {
+                match chars.clone().next() {
+                    Some(')') => {
+                        chars.next();
+                        break;
+                    }
+                    None => return Err(Error::InvalidSignature),
+                    _ => {
+                        parse_complete_type(chars)?;
+                        saw_member = true;
+                    }
+                }
+            }
+            if saw_member {
+                Ok(())
+            } else {
+                Err(Error::InvalidSignature)
+            }
+        }
+        '{' => {
+            // Dict-entry keys must be a basic (non-container) type.
+            match chars.next().ok_or(Error::InvalidSignature)? {
+                'y' | 'b' | 'n' | 'q' | 'i' | 'u' | 'x' | 't' | 'd' | 's' | 'o' | 'g' | 'h' => {}
+                _ => return Err(Error::InvalidSignature),
+            }
+            parse_complete_type(chars)?;
+            match chars.next() {
+                Some('}') => Ok(()),
+                _ => Err(Error::InvalidSignature),
+            }
+        }
+        _ => Err(Error::InvalidSignature),
+    }
+}
@@ -0,0 +1,860 @@
+//! Marshalling and unmarshalling of `Message` to and from the D-Bus wire format.
+
+use crate::fd::OwnedFd;
+use crate::message::{
+    Base, ByteOrder, Container, Error, HeaderField, HeaderFlags, Message, MessageType, Param,
+    Result, Variant,
+};
+use std::os::unix::io::RawFd;
+
+fn message_type_code(typ: MessageType) -> u8 {
+    match typ {
+        MessageType::Call => 1,
+        MessageType::Reply => 2,
+        MessageType::Error => 3,
+        MessageType::Signal => 4,
+    }
+}
+
+fn message_type_from_code(code: u8) -> Result<MessageType> {
+    match code {
+        1 => Ok(MessageType::Call),
+        2 => Ok(MessageType::Reply),
+        3 => Ok(MessageType::Error),
+        4 => Ok(MessageType::Signal),
+        _ => Err(Error::InvalidHeaderFields),
+    }
+}
+
+fn header_field_code(field: &HeaderField) -> u8 {
+    match field {
+        HeaderField::Path(_) => 1,
+        HeaderField::Interface(_) => 2,
+        HeaderField::Member(_) => 3,
+        HeaderField::ErrorName(_) => 4,
+        HeaderField::ReplySerial(_) => 5,
+        HeaderField::Destination(_) => 6,
+        HeaderField::Sender(_) => 7,
+        HeaderField::Signature(_) => 8,
+        HeaderField::UnixFds(_) => 9,
+    }
+}
+
+fn align(buf: &mut Vec<u8>, alignment: usize) {
+    while buf.len() % alignment != 0 {
+        buf.push(0);
+    }
+}
+
+/// Alignment in bytes for a single D-Bus type-signature code.
+fn alignment_of_code(code: char) -> usize {
+    match code {
+        'y' | 'g' | 'v' => 1,
+        'n' | 'q' => 2,
+        'i' | 'u' | 'b' | 'h' => 4,
+        'x' | 't' | 'd' => 8,
+        's' | 'o' => 4,
+        'a' => 4,
+        '(' | '{' => 8,
+        _ => 1,
+    }
+}
+
+fn write_u16(buf: &mut Vec<u8>, val: u16, order: ByteOrder) {
+    align(buf, 2);
+    match order {
+        ByteOrder::LittleEndian => buf.extend_from_slice(&val.to_le_bytes()),
+        ByteOrder::BigEndian => buf.extend_from_slice(&val.to_be_bytes()),
+    }
+}
+
+fn write_u32(buf: &mut Vec<u8>, val: u32, order: ByteOrder) {
+    align(buf, 4);
+    match order {
+        ByteOrder::LittleEndian => buf.extend_from_slice(&val.to_le_bytes()),
+        ByteOrder::BigEndian => buf.extend_from_slice(&val.to_be_bytes()),
+    }
+}
+
+fn write_u64(buf: &mut Vec<u8>, val: u64, order: ByteOrder) {
+    align(buf, 8);
+    match order {
+        ByteOrder::LittleEndian => buf.extend_from_slice(&val.to_le_bytes()),
+        ByteOrder::BigEndian => buf.extend_from_slice(&val.to_be_bytes()),
+    }
+}
+
+fn write_string(buf: &mut Vec<u8>, s: &str, order: ByteOrder) {
+    write_u32(buf, s.len() as u32, order);
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+}
+
+/// Writes a D-Bus signature string, whose length prefix is a single byte.
+///
+/// Errors with `Error::InvalidSignature` if `s` is longer than 255 bytes, since
+/// that length can't be represented without truncation.
+fn write_signature_str(buf: &mut Vec<u8>, s: &str) -> Result<()> {
+    if s.len() > u8::MAX as usize {
+        return Err(Error::InvalidSignature);
+    }
+    buf.push(s.len() as u8);
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+    Ok(())
+}
+
+fn write_base(buf: &mut Vec<u8>, base: &Base, order: ByteOrder, fds: &mut Vec<RawFd>) -> Result<()> {
+    match base {
+        Base::Byte(v) => buf.push(*v),
+        Base::Int16(v) => write_u16(buf, *v as u16, order),
+        Base::Uint16(v) => write_u16(buf, *v, order),
+        Base::Int32(v) => write_u32(buf, *v as u32, order),
+        Base::Uint32(v) => write_u32(buf, *v, order),
+        Base::Boolean(v) => write_u32(buf, if *v { 1 } else { 0 }, order),
+        Base::Int64(v) => write_u64(buf, *v as u64, order),
+        Base::Uint64(v) => write_u64(buf, *v, order),
+        Base::Double(v) => write_u64(buf, v.to_bits(), order),
+        Base::String(s) | Base::ObjectPath(s) => write_string(buf, s, order),
+        Base::Signature(s) => write_signature_str(buf, s)?,
+        Base::UnixFd(owned) => {
+            let index = fds.len() as u32;
+            fds.push(owned.as_raw_fd());
+            write_u32(buf, index, order);
+        }
+    }
+    Ok(())
+}
+
+fn write_param(
+    buf: &mut Vec<u8>,
+    param: &Param,
+    order: ByteOrder,
+    fds: &mut Vec<RawFd>,
+) -> Result<()> {
+    match param {
+        Param::Base(b) => write_base(buf, b, order, fds)?,
+        Param::Container(Container::Array(elem_sig, elements)) => {
+            align(buf, 4);
+            let len_pos = buf.len();
+            write_u32(buf, 0, order);
+            // The array body is padded to its element type's alignment even when
+            // `elements` is empty, since `elem_sig` (not an element instance) is
+            // what fixes that alignment.
+            let elem_code = elem_sig.as_str().chars().next().ok_or(Error::InvalidSignature)?;
+            align(buf, alignment_of_code(elem_code));
+            let body_start = buf.len();
+            for el in elements {
+                write_param(buf, el, order, fds)?;
+            }
+            let body_len = (buf.len() - body_start) as u32;
+            let bytes = match order {
+                ByteOrder::LittleEndian => body_len.to_le_bytes(),
+                ByteOrder::BigEndian => body_len.to_be_bytes(),
+            };
+            buf[len_pos..len_pos + 4].copy_from_slice(&bytes);
+        }
+        Param::Container(Container::Struct(elements)) => {
+            align(buf, 8);
+            for el in elements {
+                write_param(buf, el, order, fds)?;
+            }
+        }
+        Param::Container(Container::DictEntry(key, val)) => {
+            align(buf, 8);
+            write_base(buf, key, order, fds)?;
+            write_param(buf, val, order, fds)?;
+        }
+        Param::Container(Container::Variant(variant)) => {
+            let mut sig = String::new();
+            variant.value.make_signature(&mut sig)?;
+            write_signature_str(buf, &sig)?;
+            write_param(buf, &variant.value, order, fds)?;
+        }
+    }
+    Ok(())
+}
+
+/// Recursively counts how many `Base::UnixFd` values a set of params carries.
+fn count_unix_fds(params: &[Param]) -> u32 {
+    fn count_base(base: &Base) -> u32 {
+        match base {
+            Base::UnixFd(_) => 1,
+            _ => 0,
+        }
+    }
+    fn count_param(param: &Param) -> u32 {
+        match param {
+            Param::Base(b) => count_base(b),
+            Param::Container(Container::Array(_, elements))
+            | Param::Container(Container::Struct(elements)) => {
+                elements.iter().map(count_param).sum()
+            }
+            Param::Container(Container::DictEntry(key, val)) => {
+                count_base(key) + count_param(val)
+            }
+            Param::Container(Container::Variant(variant)) => count_param(&variant.value),
+        }
+    }
+    params.iter().map(count_param).sum()
+}
+
+fn write_header_field(buf: &mut Vec<u8>, field: &HeaderField, order: ByteOrder) -> Result<()> {
+    align(buf, 8);
+    buf.push(header_field_code(field));
+    match field {
+        HeaderField::Path(s) => {
+            write_signature_str(buf, "o")?;
+            write_string(buf, s, order);
+        }
+        HeaderField::Interface(s)
+        | HeaderField::Member(s)
+        | HeaderField::ErrorName(s)
+        | HeaderField::Destination(s)
+        | HeaderField::Sender(s) => {
+            write_signature_str(buf, "s")?;
+            write_string(buf, s, order);
+        }
+        HeaderField::Signature(s) => {
+            write_signature_str(buf, "g")?;
+            write_signature_str(buf, s)?;
+        }
+        HeaderField::ReplySerial(v) | HeaderField::UnixFds(v) => {
+            write_signature_str(buf, "u")?;
+            write_u32(buf, *v, order);
+        }
+    }
+    Ok(())
+}
+
+/// Serializes `msg` to the D-Bus wire format, using `serial` as its message serial.
+///
+/// Returns the marshalled bytes together with the raw file descriptors referenced by
+/// any `Base::UnixFd` values in the body, in the order their indices were assigned;
+/// these must be sent alongside the message as SCM_RIGHTS ancillary data.
+pub fn marshal(msg: &Message, order: ByteOrder, serial: u32) -> Result<(Vec<u8>, Vec<RawFd>)> {
+    let mut header_fields = Vec::new();
+    if let Some(path) = &msg.object {
+        header_fields.push(HeaderField::Path(path.clone()));
+    }
+    if let Some(interface) = &msg.interface {
+        header_fields.push(HeaderField::Interface(interface.clone()));
+    }
+    if let Some(member) = &msg.member {
+        header_fields.push(HeaderField::Member(member.clone()));
+    }
+    if let Some(destination) = &msg.destination {
+        header_fields.push(HeaderField::Destination(destination.clone()));
+    }
+    if let Some(sender) = &msg.sender {
+        header_fields.push(HeaderField::Sender(sender.clone()));
+    }
+    if let Some(error_name) = &msg.error_name {
+        header_fields.push(HeaderField::ErrorName(error_name.clone()));
+    }
+    if let Some(reply_serial) = msg.reply_serial {
+        header_fields.push(HeaderField::ReplySerial(reply_serial));
+    }
+    if !msg.params.is_empty() {
+        let mut sig = String::new();
+        for param in &msg.params {
+            param.make_signature(&mut sig)?;
+        }
+        header_fields.push(HeaderField::Signature(sig));
+    }
+    let fd_count = count_unix_fds(&msg.params);
+    if fd_count > 0 {
+        header_fields.push(HeaderField::UnixFds(fd_count));
+    }
+    crate::message::validate_header_fields(msg.typ, &header_fields)?;
+
+    let mut fds = Vec::new();
+    let mut body = Vec::new();
+    for param in &msg.params {
+        write_param(&mut body, param, order, &mut fds)?;
+    }
+
+    let mut buf = Vec::new();
+    buf.push(match order {
+        ByteOrder::LittleEndian => b'l',
+        ByteOrder::BigEndian => b'B',
+    });
+    buf.push(message_type_code(msg.typ));
+    buf.push(msg.flags.iter().fold(0, |acc, f| acc | f.bit()));
+    buf.push(1); // protocol version
+    write_u32(&mut buf, body.len() as u32, order);
+    write_u32(&mut buf, serial, order);
+
+    let array_len_pos = buf.len();
+    write_u32(&mut buf, 0, order);
+    let array_start = buf.len();
+    for field in &header_fields {
+        write_header_field(&mut buf, field, order)?;
+    }
+    let array_len = (buf.len() - array_start) as u32;
+    let bytes = match order {
+        ByteOrder::LittleEndian => array_len.to_le_bytes(),
+        ByteOrder::BigEndian => array_len.to_be_bytes(),
+    };
+    buf[array_len_pos..array_len_pos + 4].copy_from_slice(&bytes);
+
+    align(&mut buf, 8);
+    buf.extend_from_slice(&body);
+    Ok((buf, fds))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    order: ByteOrder,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], order: ByteOrder) -> Self {
+        Reader { bytes, pos: 0, order }
+    }
+
+    fn align(&mut self, alignment: usize) -> Result<()> {
+        let padding = (alignment - (self.pos % alignment)) % alignment;
+        self.skip(padding)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<()> {
+        if self.pos + count > self.bytes.len() {
+            return Err(Error::InvalidSignature);
+        }
+        self.pos += count;
+        Ok(())
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        if self.pos >= self.bytes.len() {
+            return Err(Error::InvalidSignature);
+        }
+        let b = self.bytes[self.pos];
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        if self.pos + count > self.bytes.len() {
+            return Err(Error::InvalidSignature);
+        }
+        let slice = &self.bytes[self.pos..self.pos + count];
+        self.pos += count;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        self.align(2)?;
+        let b = self.take(2)?;
+        Ok(match self.order {
+            ByteOrder::LittleEndian => u16::from_le_bytes([b[0], b[1]]),
+            ByteOrder::BigEndian => u16::from_be_bytes([b[0], b[1]]),
+        })
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        self.align(4)?;
+        let b = self.take(4)?;
+        Ok(match self.order {
+            ByteOrder::LittleEndian => u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            ByteOrder::BigEndian => u32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+        })
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        self.align(8)?;
+        let b = self.take(8)?;
+        let arr: [u8; 8] = b.try_into().map_err(|_| Error::InvalidSignature)?;
+        Ok(match self.order {
+            ByteOrder::LittleEndian => u64::from_le_bytes(arr),
+            ByteOrder::BigEndian => u64::from_be_bytes(arr),
+        })
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?.to_vec();
+        self.skip(1)?; // trailing NUL
+        String::from_utf8(bytes).map_err(|_| Error::InvalidSignature)
+    }
+
+    fn signature_str(&mut self) -> Result<String> {
+        let len = self.byte()? as usize;
+        let bytes = self.take(len)?.to_vec();
+        self.skip(1)?; // trailing NUL
+        String::from_utf8(bytes).map_err(|_| Error::InvalidSignature)
+    }
+}
+
+/// Consumes one complete type signature (e.g. `a{sv}`, `(ii)`, `s`) from `chars`
+/// and returns it, leaving `chars` positioned right after it.
+fn take_single_type(chars: &mut std::str::Chars) -> Result<String> {
+    let mut out = String::new();
+    let first = chars.next().ok_or(Error::InvalidSignature)?;
+    out.push(first);
+    match first {
+        'a' => {
+            out.push_str(&take_single_type(chars)?);
+        }
+        '(' => {
+            loop {
+                let mut lookahead = chars.clone();
+                match lookahead.next() {
+                    Some(')') => {
+                        chars.next();
+                        out.push(')');
+                        break;
+                    }
+                    None => return Err(Error::InvalidSignature),
+                    _ => out.push_str(&take_single_type(chars)?),
+                }
+            }
+        }
+        '{' => {
+            out.push_str(&take_single_type(chars)?); // key
+            out.push_str(&take_single_type(chars)?); // value
+            match chars.next() {
+                Some('}') => out.push('}'),
+                _ => return Err(Error::InvalidSignature),
+            }
+        }
+        _ => {}
+    }
+    Ok(out)
+}
+
+fn read_base(reader: &mut Reader, code: char, fds: &[OwnedFd]) -> Result<Base> {
+    Ok(match code {
+        'y' => Base::Byte(reader.byte()?),
+        'n' => Base::Int16(reader.u16()? as i16),
+        'q' => Base::Uint16(reader.u16()?),
+        'b' => Base::Boolean(reader.u32()? != 0),
+        'i' => Base::Int32(reader.u32()? as i32),
+        'u' => Base::Uint32(reader.u32()?),
+        'x' => Base::Int64(reader.u64()? as i64),
+        't' => Base::Uint64(reader.u64()?),
+        'd' => Base::Double(f64::from_bits(reader.u64()?)),
+        's' => Base::String(reader.string()?),
+        'o' => {
+            let path = reader.string()?;
+            crate::message::validate_object_path(&path)?;
+            Base::ObjectPath(path)
+        }
+        'g' => Base::Signature(reader.signature_str()?),
+        'h' => {
+            let index = reader.u32()? as usize;
+            let owned = fds.get(index).ok_or(Error::InvalidSignature)?;
+            Base::UnixFd(owned.try_clone().map_err(Error::FdDupFailed)?)
+        }
+        _ => return Err(Error::InvalidSignature),
+    })
+}
+
+fn read_param(reader: &mut Reader, sig: &str, fds: &[OwnedFd]) -> Result<Param> {
+    let mut chars = sig.chars();
+    let code = chars.next().ok_or(Error::InvalidSignature)?;
+    Ok(match code {
+        'a' => {
+            let elem_sig = take_single_type(&mut chars)?;
+            let elem_code = elem_sig.chars().next().ok_or(Error::InvalidSignature)?;
+            let byte_len = reader.u32()? as usize;
+            reader.align(alignment_of_code(elem_code))?;
+            let end = reader.pos + byte_len;
+            let mut elements = Vec::new();
+            while reader.pos < end {
+                elements.push(read_param(reader, &elem_sig, fds)?);
+            }
+            let elem_type =
+                crate::signature::Type::from_str(&elem_sig).map_err(|_| Error::InvalidSignature)?;
+            Param::Container(Container::Array(elem_type, elements))
+        }
+        '(' => {
+            reader.align(8)?;
+            let mut elements = Vec::new();
+            let inner = &sig[1..sig.len() - 1];
+            let mut inner_chars = inner.chars();
+            loop {
+                let remaining = inner_chars.as_str();
+                if remaining.is_empty() {
+                    break;
+                }
+                let mut peek = remaining.chars();
+                let elem_sig = take_single_type(&mut peek)?;
+                elements.push(read_param(reader, &elem_sig, fds)?);
+                for _ in elem_sig.chars() {
+                    inner_chars.next();
+                }
+            }
+            Param::Container(Container::Struct(elements))
+        }
+        '{' => {
+            reader.align(8)?;
+            let inner = &sig[1..sig.len() - 1];
+            let mut inner_chars = inner.chars();
+            let key_sig = take_single_type(&mut inner_chars)?;
+            let key_code = key_sig.chars().next().ok_or(Error::InvalidSignature)?;
+            let key = read_base(reader, key_code, fds)?;
+            let val_sig: String = inner_chars.as_str().to_string();
+            let val = read_param(reader, &val_sig, fds)?;
+            Param::Container(Container::DictEntry(key, Box::new(val)))
+        }
+        'v' => {
+            let variant_sig = reader.signature_str()?;
+            let value = read_param(reader, &variant_sig, fds)?;
+            Param::Container(Container::Variant(Box::new(Variant {
+                sig: crate::signature::Type::from_str(&variant_sig)
+                    .map_err(|_| Error::InvalidSignature)?,
+                value,
+            })))
+        }
+        other => Param::Base(read_base(reader, other, fds)?),
+    })
+}
+
+/// Parses a D-Bus wire-format message out of `bytes`, resolving any `'h'`-typed
+/// values against `fds` (the file descriptors received as SCM_RIGHTS ancillary
+/// data alongside the message bytes, indexed by the values the body carries).
+///
+/// Returns `Error::FdDupFailed` rather than panicking if duplicating a resolved
+/// `'h'` value's descriptor fails, e.g. because the process has run out of file
+/// descriptors.
+pub fn unmarshal(bytes: &[u8], fds: &[OwnedFd]) -> Result<Message> {
+    if bytes.len() < 12 {
+        return Err(Error::InvalidSignature);
+    }
+    let order = match bytes[0] {
+        b'l' => ByteOrder::LittleEndian,
+        b'B' => ByteOrder::BigEndian,
+        _ => return Err(Error::InvalidSignature),
+    };
+    let mut reader = Reader::new(bytes, order);
+    reader.byte()?; // endianness, already inspected above
+    let typ = message_type_from_code(reader.byte()?)?;
+    let flags_byte = reader.byte()?;
+    reader.byte()?; // protocol version
+    let body_len = reader.u32()?;
+    reader.u32()?; // serial
+
+    let fields_len = reader.u32()? as usize;
+    let fields_start = reader.pos;
+    let mut header_fields = Vec::new();
+    while reader.pos < fields_start + fields_len {
+        reader.align(8)?;
+        let code = reader.byte()?;
+        let sig = reader.signature_str()?;
+        let field = match code {
+            1 => {
+                let path = reader.string()?;
+                crate::message::validate_object_path(&path)?;
+                HeaderField::Path(path)
+            }
+            2 => HeaderField::Interface(reader.string()?),
+            3 => HeaderField::Member(reader.string()?),
+            4 => HeaderField::ErrorName(reader.string()?),
+            5 => HeaderField::ReplySerial(reader.u32()?),
+            6 => HeaderField::Destination(reader.string()?),
+            7 => HeaderField::Sender(reader.string()?),
+            8 => HeaderField::Signature(reader.signature_str()?),
+            9 => HeaderField::UnixFds(reader.u32()?),
+            _ => return Err(Error::InvalidHeaderFields),
+        };
+        let _ = sig;
+        header_fields.push(field);
+    }
+    crate::message::validate_header_fields(typ, &header_fields)?;
+
+    reader.align(8)?;
+    let body_start = reader.pos;
+    let body_sig = header_fields.iter().find_map(|f| match f {
+        HeaderField::Signature(s) => Some(s.clone()),
+        _ => None,
+    });
+
+    let mut params = Vec::new();
+    if let Some(sig) = body_sig {
+        let mut chars = sig.chars();
+        while reader.pos < body_start + body_len as usize {
+            let remaining = chars.as_str();
+            if remaining.is_empty() {
+                break;
+            }
+            let mut peek = remaining.chars();
+            let elem_sig = take_single_type(&mut peek)?;
+            params.push(read_param(&mut reader, &elem_sig, fds)?);
+            for _ in elem_sig.chars() {
+                chars.next();
+            }
+        }
+    }
+
+    let mut interface = None;
+    let mut member = None;
+    let mut object = None;
+    let mut destination = None;
+    let mut sender = None;
+    let mut error_name = None;
+    let mut reply_serial = None;
+    for field in header_fields {
+        match field {
+            HeaderField::Interface(s) => interface = Some(s),
+            HeaderField::Member(s) => member = Some(s),
+            HeaderField::Path(s) => object = Some(s),
+            HeaderField::Destination(s) => destination = Some(s),
+            HeaderField::Sender(s) => sender = Some(s),
+            HeaderField::ErrorName(s) => error_name = Some(s),
+            HeaderField::ReplySerial(v) => reply_serial = Some(v),
+            _ => {}
+        }
+    }
+
+    let mut msg = Message::new(typ, interface, member, object, destination, params);
+    msg.sender = sender;
+    msg.error_name = error_name;
+    msg.reply_serial = reply_serial;
+    msg.flags = [
+        HeaderFlags::NoReplyExpected,
+        HeaderFlags::NoAutoStart,
+        HeaderFlags::AllowInteractiveAuthorization,
+    ]
+    .into_iter()
+    .filter(|f| flags_byte & f.bit() != 0)
+    .collect();
+    Ok(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arg::Append;
+    use crate::builder::MessageBuilder;
+    use std::collections::HashMap;
+    use std::os::unix::io::IntoRawFd;
+
+    fn roundtrip(msg: &Message) -> Message {
+        let (bytes, fds) = marshal(msg, ByteOrder::LittleEndian, 1).unwrap();
+        let owned_fds: Vec<OwnedFd> = fds
+            .into_iter()
+            .map(|fd| OwnedFd::new(unsafe { libc_dup(fd) }))
+            .collect();
+        unmarshal(&bytes, &owned_fds).unwrap()
+    }
+
+    // Local, test-only `dup` so round-tripping a `UnixFd` doesn't have to fight
+    // over ownership of the original message's descriptor with the unmarshalled copy.
+    unsafe fn libc_dup(fd: std::os::unix::io::RawFd) -> std::os::unix::io::RawFd {
+        extern "C" {
+            fn dup(fd: i32) -> i32;
+        }
+        dup(fd)
+    }
+
+    #[test]
+    fn roundtrip_basic_types() {
+        let msg = MessageBuilder::method_call("/test", "Method")
+            .append(1u8.append())
+            .append((-2i16).append())
+            .append(3u16.append())
+            .append((-4i32).append())
+            .append(5u32.append())
+            .append((-6i64).append())
+            .append(7u64.append())
+            .append(8.5f64.append())
+            .append(true.append())
+            .append("hello".to_string().append())
+            .append(Param::Base(Base::ObjectPath("/a/b".to_string())))
+            .append(Param::Base(Base::Signature("ai".to_string())))
+            .build()
+            .unwrap();
+
+        let back = roundtrip(&msg);
+        assert_eq!(back.get::<u8>(0).unwrap(), 1);
+        assert_eq!(back.get::<i16>(1).unwrap(), -2);
+        assert_eq!(back.get::<u16>(2).unwrap(), 3);
+        assert_eq!(back.get::<i32>(3).unwrap(), -4);
+        assert_eq!(back.get::<u32>(4).unwrap(), 5);
+        assert_eq!(back.get::<i64>(5).unwrap(), -6);
+        assert_eq!(back.get::<u64>(6).unwrap(), 7);
+        assert_eq!(back.get::<f64>(7).unwrap(), 8.5);
+        assert!(back.get::<bool>(8).unwrap());
+        assert_eq!(back.get::<String>(9).unwrap(), "hello");
+        match &back.params[10] {
+            Param::Base(Base::ObjectPath(p)) => assert_eq!(p, "/a/b"),
+            other => panic!("expected ObjectPath, got {other:?}"),
+        }
+        match &back.params[11] {
+            Param::Base(Base::Signature(s)) => assert_eq!(s, "ai"),
+            other => panic!("expected Signature, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_array() {
+        let msg = MessageBuilder::method_call("/test", "Method")
+            .append(vec![1i32, 2, 3].append())
+            .build()
+            .unwrap();
+
+        let back = roundtrip(&msg);
+        assert_eq!(back.get::<Vec<i32>>(0).unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn roundtrip_empty_array() {
+        // An empty array is ordinary on the wire; it must marshal and unmarshal
+        // using the element signature `Vec<T>::append` carries explicitly, with
+        // no element instance available to derive it from.
+        let msg = MessageBuilder::method_call("/test", "Method")
+            .append(Vec::<i32>::new().append())
+            .build()
+            .unwrap();
+
+        let back = roundtrip(&msg);
+        assert_eq!(back.get::<Vec<i32>>(0).unwrap(), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn roundtrip_struct() {
+        let msg = MessageBuilder::method_call("/test", "Method")
+            .append((1i32, "two".to_string(), true).append())
+            .build()
+            .unwrap();
+
+        let back = roundtrip(&msg);
+        assert_eq!(
+            back.get::<(i32, String, bool)>(0).unwrap(),
+            (1, "two".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn roundtrip_dict_entry() {
+        let mut map = HashMap::new();
+        map.insert(1i32, "one".to_string());
+        let msg = MessageBuilder::method_call("/test", "Method")
+            .append(map.append())
+            .build()
+            .unwrap();
+
+        let back = roundtrip(&msg);
+        let map_back = back.get::<HashMap<i32, String>>(0).unwrap();
+        assert_eq!(map_back.get(&1), Some(&"one".to_string()));
+    }
+
+    #[test]
+    fn roundtrip_variant() {
+        let variant = Param::Container(Container::Variant(Box::new(Variant {
+            sig: crate::signature::Type::from_str("i").unwrap(),
+            value: Param::Base(Base::Int32(42)),
+        })));
+        let msg = MessageBuilder::method_call("/test", "Method")
+            .append(variant)
+            .build()
+            .unwrap();
+
+        let back = roundtrip(&msg);
+        match &back.params[0] {
+            Param::Container(Container::Variant(v)) => match &v.value {
+                Param::Base(Base::Int32(n)) => assert_eq!(*n, 42),
+                other => panic!("expected Int32 variant, got {other:?}"),
+            },
+            other => panic!("expected Variant, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_unix_fd() {
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let msg = MessageBuilder::method_call("/test", "Method")
+            .append(Param::Base(Base::UnixFd(OwnedFd::new(file.into_raw_fd()))))
+            .build()
+            .unwrap();
+
+        let back = roundtrip(&msg);
+        match &back.params[0] {
+            Param::Base(Base::UnixFd(owned)) => assert!(owned.as_raw_fd() >= 0),
+            other => panic!("expected UnixFd, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn marshal_rejects_oversized_signature() {
+        // 90 `(i)` structs is a 270-byte combined signature, over the wire
+        // format's 255-byte signature length limit.
+        let mut builder = MessageBuilder::method_call("/test", "Method");
+        for _ in 0..90 {
+            builder = builder.append(Param::Container(Container::Struct(vec![Param::Base(
+                Base::Int32(0),
+            )])));
+        }
+        let msg = builder.build().unwrap();
+
+        assert!(matches!(
+            marshal(&msg, ByteOrder::LittleEndian, 1),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn count_unix_fds_counts_dict_entry_keys() {
+        // `'h'` is a legal dict-entry key type per this crate's own signature
+        // grammar, and `write_base` pushes a raw fd for a key just like it does
+        // for a value, so the key must be counted too.
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let params = vec![Param::Container(Container::DictEntry(
+            Base::UnixFd(OwnedFd::new(file.into_raw_fd())),
+            Box::new(Param::Base(Base::Int32(0))),
+        ))];
+        assert_eq!(count_unix_fds(&params), 1);
+    }
+
+    #[test]
+    fn unmarshal_rejects_truncated_buffer() {
+        assert!(matches!(
+            unmarshal(&[b'l', 1, 0, 1], &[]),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn unmarshal_rejects_bad_message_type_code() {
+        let msg = MessageBuilder::method_call("/test", "Method").build().unwrap();
+        let (mut bytes, _fds) = marshal(&msg, ByteOrder::LittleEndian, 1).unwrap();
+        bytes[1] = 0xff; // no such MessageType
+        assert!(matches!(
+            unmarshal(&bytes, &[]),
+            Err(Error::InvalidHeaderFields)
+        ));
+    }
+
+    #[test]
+    fn unmarshal_rejects_corrupt_signature() {
+        let msg = MessageBuilder::method_call("/test", "Method")
+            .append(1i32.append())
+            .build()
+            .unwrap();
+        let (mut bytes, _fds) = marshal(&msg, ByteOrder::LittleEndian, 1).unwrap();
+        // Flip the signature header field's declared signature code ('i') to one
+        // this crate's grammar doesn't recognize, corrupting the body's parse.
+        let pos = bytes.windows(2).rposition(|w| w == [1, b'i']).unwrap();
+        bytes[pos + 1] = b'Z';
+        assert!(unmarshal(&bytes, &[]).is_err());
+    }
+
+    #[test]
+    fn unmarshal_reports_fd_dup_failure() {
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let msg = MessageBuilder::method_call("/test", "Method")
+            .append(Param::Base(Base::UnixFd(OwnedFd::new(file.into_raw_fd()))))
+            .build()
+            .unwrap();
+        let (bytes, _fds) = marshal(&msg, ByteOrder::LittleEndian, 1).unwrap();
+
+        let bad_fds = vec![OwnedFd::new(-1)];
+        assert!(matches!(
+            unmarshal(&bytes, &bad_fds),
+            Err(Error::FdDupFailed(_))
+        ));
+    }
+}